@@ -1,16 +1,23 @@
-use std::{net::IpAddr, sync::{Mutex, Arc}};
+use std::sync::{Arc, Mutex};
 
-use crate::{peer::Peer, packet::{ControlPacket, ControlPacketType}};
+use crate::{
+    packet::{ControlPacket, ControlPacketType},
+    peer::Peer,
+    routing_table::RoutingTable,
+    subnet::Subnet,
+};
 
 #[derive(Debug)]
 pub struct Router {
     pub directly_connected_peers: Arc<Mutex<Vec<Peer>>>,
+    routing_table: RoutingTable,
 }
 
 impl Router {
     pub fn new() -> Self {
         Router {
             directly_connected_peers: Arc::new(Mutex::new(Vec::new())),
+            routing_table: RoutingTable::new(),
         }
     }
 
@@ -29,24 +36,17 @@ impl Router {
             peer.to_peer_control.send(hello_message.clone());
         }
     }
-}
-
-struct Route {
-    prefix: u8,
-    plen: u8,
-    neighbour: Peer,
-}
 
-struct RouteEntry {
-    source: (u8, u8, u16), // source (prefix, plen, router-id) for which this route is advertised
-    neighbour: Peer, // neighbour that advertised this route
-    metric: u16, // metric of this route as advertised by the neighbour 
-    seqno: u16, // sequence number of this route as advertised by the neighbour
-    next_hop: IpAddr, // next-hop for this route
-    selected: bool, // whether this route is selected
+    /// Returns directly connected peers that can act as a relay toward `subnet`, for use when
+    /// this node has no selected route for it directly. The forwarding layer can splice packets
+    /// through the best-ranked candidate instead of dropping them.
+    pub fn potential_relays(&self, subnet: Subnet) -> Vec<Peer> {
+        if self.routing_table.has_selected_route(subnet) {
+            return Vec::new();
+        }
 
-    // each route table entry needs a route expiry timer
-    // each route has two distinct (seqno, metric) pairs associated with it:
-    // 1. (seqno, metric): describes the route's distance
-    // 2. (seqno, metric): describes the feasibility distance (should be stored in source table and shared between all routes with the same source)
-}
\ No newline at end of file
+        let directly_connected_peers = self.directly_connected_peers.lock().unwrap();
+        self.routing_table
+            .potential_relays(subnet, &directly_connected_peers)
+    }
+}
@@ -1,8 +1,17 @@
 use crate::{
     metric::Metric, peer::Peer, router_id::RouterId, sequence_number::SeqNo,
-    source_table::SourceKey, subnet::Subnet,
+    source_table::{SourceKey, SourceTable},
+    subnet::Subnet,
+};
+use std::{
+    cmp::Ordering,
+    collections::{hash_map::DefaultHasher, BTreeMap, BTreeSet},
+    hash::{Hash, Hasher},
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    ops::{Deref, DerefMut, RangeInclusive},
+    sync::{RwLock, RwLockWriteGuard},
+    time::{Duration, Instant},
 };
-use std::{cmp::Ordering, collections::BTreeMap, net::IpAddr};
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct RouteKey {
@@ -38,6 +47,13 @@ pub struct RouteEntry {
     metric: Metric, // If metric is 0xFFFF, the route has recently been retracted
     seqno: SeqNo,
     selected: bool,
+    // Route expiry timer: the hold time is derived from the advertising neighbour's hello/
+    // update interval, and `last_updated` is reset every time a matching update is received.
+    hold_time: Duration,
+    last_updated: Instant,
+    // Set once the entry's hold time elapses and it is retracted, so garbage collection can
+    // give neighbours a further delay to observe the retraction before the entry is dropped.
+    retracted_at: Option<Instant>,
 }
 
 impl RouteKey {
@@ -52,16 +68,27 @@ impl RouteKey {
     pub const fn subnet(&self) -> Subnet {
         self.subnet
     }
+
+    /// Returns the key under which this `RouteKey` is stored in a [`Shard`]'s table: the subnet
+    /// together with the neighbour's overlay IP, which is exactly what `RouteKey`'s own `Ord`
+    /// impl compares by. Bounding a range query by these tuples doesn't require a real [`Peer`],
+    /// unlike bounding one by `RouteKey` itself.
+    #[inline]
+    fn table_key(&self) -> (Subnet, IpAddr) {
+        (self.subnet, self.neighbor.overlay_ip())
+    }
 }
 
 impl RouteEntry {
-    /// Create a new `RouteEntry`.
-    pub const fn new(
+    /// Create a new `RouteEntry`, with its hold time set to `hold_time` and its expiry timer
+    /// starting now.
+    pub fn new(
         source: SourceKey,
         neighbor: Peer,
         metric: Metric,
         seqno: SeqNo,
         selected: bool,
+        hold_time: Duration,
     ) -> Self {
         Self {
             source,
@@ -69,6 +96,9 @@ impl RouteEntry {
             metric,
             seqno,
             selected,
+            hold_time,
+            last_updated: Instant::now(),
+            retracted_at: None,
         }
     }
 
@@ -82,6 +112,11 @@ impl RouteEntry {
         self.metric
     }
 
+    /// Returns the seqno associated with this `RouteEntry`.
+    pub const fn seqno(&self) -> SeqNo {
+        self.seqno
+    }
+
     /// Return the (neighbour)[`Peer`] associated with this `RouteEntry`.
     pub fn neighbour(&self) -> &Peer {
         &self.neighbor
@@ -107,77 +142,987 @@ impl RouteEntry {
         self.source.set_router_id(router_id);
     }
 
+    /// Returns the hold time of this `RouteEntry`.
+    pub const fn hold_time(&self) -> Duration {
+        self.hold_time
+    }
+
+    /// Resets the expiry timer of this `RouteEntry`, as if a fresh update had just been
+    /// received for it.
+    pub fn refresh(&mut self) {
+        self.last_updated = Instant::now();
+        self.retracted_at = None;
+    }
+
+    /// Checks whether this `RouteEntry`'s hold time has elapsed as of `now`.
+    fn expired(&self, now: Instant) -> bool {
+        self.retracted_at.is_none() && now.saturating_duration_since(self.last_updated) >= self.hold_time
+    }
+
+    /// Marks this `RouteEntry` as retracted: its metric is set to infinite, it stops being the
+    /// selected route, and its garbage collection timer starts.
+    fn retract(&mut self, now: Instant) {
+        self.metric = Metric::infinite();
+        self.selected = false;
+        self.retracted_at = Some(now);
+    }
+
+    /// Checks whether this `RouteEntry` has been retracted for at least `gc_delay`, i.e. it is
+    /// due to be garbage collected.
+    fn collectible(&self, now: Instant, gc_delay: Duration) -> bool {
+        match self.retracted_at {
+            Some(at) => now.saturating_duration_since(at) >= gc_delay,
+            None => false,
+        }
+    }
+
     /// Sets whether or not this `RouteEntry` is the selected route for the associated [`Peer`].
     pub fn set_selected(&mut self, selected: bool) {
         self.selected = selected
     }
 }
 
-#[derive(Debug, Clone)]
+/// A compact path-compressed (patricia) trie used to accelerate longest-prefix-match lookups on the
+/// forwarding path. It is purely a derived index: the [`BTreeMap`] in [`RoutingTable`] remains
+/// the authoritative store, and the trie only ever holds the [`RouteKey`] of the currently
+/// `selected` [`RouteEntry`] for a given prefix. Generic over the stored value so its core
+/// insert/remove/lookup logic can be exercised in isolation, without a [`RouteKey`].
+mod trie {
+    /// Bit-level operations needed to walk a [`PatriciaTrie`] over a fixed-width address.
+    /// Implemented for `u32` (IPv4) and `u128` (IPv6).
+    pub(super) trait PrefixBits: Copy + Eq {
+        const BITS: u32;
+
+        /// Returns the bit at `idx`, counting from the most significant bit (`idx == 0`).
+        fn bit(self, idx: u32) -> bool;
+
+        /// Masks off every bit beyond `len`, leaving the first `len` bits unchanged.
+        fn mask(self, len: u8) -> Self;
+    }
+
+    impl PrefixBits for u32 {
+        const BITS: u32 = 32;
+
+        fn bit(self, idx: u32) -> bool {
+            self & (1 << (31 - idx)) != 0
+        }
+
+        fn mask(self, len: u8) -> Self {
+            if len == 0 {
+                0
+            } else {
+                self & (u32::MAX << (32 - len as u32))
+            }
+        }
+    }
+
+    impl PrefixBits for u128 {
+        const BITS: u32 = 128;
+
+        fn bit(self, idx: u32) -> bool {
+            self & (1 << (127 - idx)) != 0
+        }
+
+        fn mask(self, len: u8) -> Self {
+            if len == 0 {
+                0
+            } else {
+                self & (u128::MAX << (128 - len as u32))
+            }
+        }
+    }
+
+    fn common_prefix_len<P: PrefixBits>(a: P, a_len: u8, b: P, b_len: u8) -> u8 {
+        let max = a_len.min(b_len);
+        let mut i = 0u8;
+        while i < max && a.bit(i as u32) == b.bit(i as u32) {
+            i += 1;
+        }
+        i
+    }
+
+    #[derive(Debug, Clone)]
+    struct Node<P: PrefixBits, V> {
+        prefix: P,
+        prefix_len: u8,
+        value: Option<V>,
+        children: [Option<Box<Node<P, V>>>; 2],
+    }
+
+    impl<P: PrefixBits, V> Node<P, V> {
+        fn leaf(prefix: P, prefix_len: u8, value: V) -> Self {
+            Self {
+                prefix,
+                prefix_len,
+                value: Some(value),
+                children: [None, None],
+            }
+        }
+
+        fn is_empty(&self) -> bool {
+            self.value.is_none() && self.children[0].is_none() && self.children[1].is_none()
+        }
+    }
+
+    /// A patricia (radix) trie keyed on address prefixes, used to find the most specific value
+    /// covering an address in O(prefix-bits) instead of scanning every entry.
+    #[derive(Debug, Clone)]
+    pub(super) struct PatriciaTrie<P: PrefixBits, V> {
+        root: Option<Box<Node<P, V>>>,
+    }
+
+    impl<P: PrefixBits, V> Default for PatriciaTrie<P, V> {
+        fn default() -> Self {
+            Self { root: None }
+        }
+    }
+
+    impl<P: PrefixBits, V: Clone> PatriciaTrie<P, V> {
+        pub(super) fn insert(&mut self, prefix: P, prefix_len: u8, value: V) {
+            let prefix = prefix.mask(prefix_len);
+            Self::insert_node(&mut self.root, prefix, prefix_len, value);
+        }
+
+        fn insert_node(
+            node: &mut Option<Box<Node<P, V>>>,
+            prefix: P,
+            prefix_len: u8,
+            value: V,
+        ) {
+            let Some(n) = node else {
+                *node = Some(Box::new(Node::leaf(prefix, prefix_len, value)));
+                return;
+            };
+
+            let common = common_prefix_len(n.prefix, n.prefix_len, prefix, prefix_len);
+            if common == n.prefix_len && common == prefix_len {
+                // Exact match for an existing node: just update its value.
+                n.value = Some(value);
+            } else if common == n.prefix_len {
+                // The new prefix is more specific than this node: descend.
+                let idx = usize::from(prefix.bit(n.prefix_len as u32));
+                Self::insert_node(&mut n.children[idx], prefix, prefix_len, value);
+            } else if common == prefix_len {
+                // The new prefix is a strict ancestor of this node: insert it above and
+                // push the existing subtree down.
+                let idx = usize::from(n.prefix.bit(prefix_len as u32));
+                let mut new_node = Box::new(Node {
+                    prefix,
+                    prefix_len,
+                    value: Some(value),
+                    children: [None, None],
+                });
+                new_node.children[idx] = node.take();
+                *node = Some(new_node);
+            } else {
+                // Neither prefix contains the other: split at the common prefix and hang
+                // both off a fresh, valueless branch node.
+                let branch_prefix = prefix.mask(common);
+                let mut branch = Box::new(Node {
+                    prefix: branch_prefix,
+                    prefix_len: common,
+                    value: None,
+                    children: [None, None],
+                });
+                let existing_idx = usize::from(n.prefix.bit(common as u32));
+                let new_idx = usize::from(prefix.bit(common as u32));
+                branch.children[new_idx] = Some(Box::new(Node::leaf(prefix, prefix_len, value)));
+                branch.children[existing_idx] = node.take();
+                *node = Some(branch);
+            }
+        }
+
+        pub(super) fn remove(&mut self, prefix: P, prefix_len: u8) {
+            let prefix = prefix.mask(prefix_len);
+            Self::remove_node(&mut self.root, prefix, prefix_len);
+        }
+
+        /// Removes the value at `prefix`/`prefix_len`, if any, and prunes nodes left empty by
+        /// the removal. Returns whether the caller's own node slot should be pruned.
+        fn remove_node(node: &mut Option<Box<Node<P, V>>>, prefix: P, prefix_len: u8) -> bool {
+            let Some(n) = node else {
+                return false;
+            };
+
+            if n.prefix_len == prefix_len && n.prefix == prefix {
+                n.value = None;
+            } else if prefix_len > n.prefix_len
+                && common_prefix_len(n.prefix, n.prefix_len, prefix, prefix_len) == n.prefix_len
+            {
+                let idx = usize::from(prefix.bit(n.prefix_len as u32));
+                if Self::remove_node(&mut n.children[idx], prefix, prefix_len) {
+                    n.children[idx] = None;
+                }
+            }
+
+            if n.is_empty() {
+                *node = None;
+                true
+            } else {
+                false
+            }
+        }
+
+        pub(super) fn lookup(&self, ip: P) -> Option<V> {
+            let mut current = &self.root;
+            let mut best = None;
+            while let Some(n) = current {
+                if ip.mask(n.prefix_len) != n.prefix {
+                    break;
+                }
+                if n.value.is_some() {
+                    best = n.value.clone();
+                }
+                if u32::from(n.prefix_len) == P::BITS {
+                    break;
+                }
+                let idx = usize::from(ip.bit(n.prefix_len as u32));
+                current = &n.children[idx];
+            }
+            best
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        /// Deterministic xorshift PRNG so the fuzz test below is reproducible without pulling
+        /// in a `rand` dependency.
+        struct Xorshift(u64);
+
+        impl Xorshift {
+            fn next(&mut self) -> u64 {
+                self.0 ^= self.0 << 13;
+                self.0 ^= self.0 >> 7;
+                self.0 ^= self.0 << 17;
+                self.0
+            }
+
+            fn next_u32(&mut self) -> u32 {
+                self.next() as u32
+            }
+
+            fn next_prefix_len(&mut self) -> u8 {
+                (self.next() % 33) as u8
+            }
+        }
+
+        /// Linear-scan oracle for longest-prefix-match, used to cross-check the trie.
+        fn oracle_lookup(routes: &[(u32, u8, u32)], ip: u32) -> Option<u32> {
+            routes
+                .iter()
+                .filter(|(prefix, len, _)| ip.mask(*len) == prefix.mask(*len))
+                .max_by_key(|(_, len, _)| *len)
+                .map(|(_, _, value)| *value)
+        }
+
+        #[test]
+        fn exact_match_wins_over_no_match() {
+            let mut trie = PatriciaTrie::<u32, u32>::default();
+            trie.insert(0x0A00_0000, 8, 1);
+            assert_eq!(trie.lookup(0x0A01_0203), Some(1));
+            assert_eq!(trie.lookup(0x0B01_0203), None);
+        }
+
+        #[test]
+        fn longest_prefix_wins_over_shorter() {
+            let mut trie = PatriciaTrie::<u32, u32>::default();
+            trie.insert(0x0A00_0000, 8, 1);
+            trie.insert(0x0A01_0000, 16, 2);
+            trie.insert(0x0A01_0200, 24, 3);
+            assert_eq!(trie.lookup(0x0A01_0201), Some(3));
+            assert_eq!(trie.lookup(0x0A01_0301), Some(2));
+            assert_eq!(trie.lookup(0x0A02_0301), Some(1));
+        }
+
+        #[test]
+        fn host_route_matches_at_full_width() {
+            let mut trie = PatriciaTrie::<u32, u32>::default();
+            trie.insert(0x0A01_0203, 32, 1);
+            assert_eq!(trie.lookup(0x0A01_0203), Some(1));
+            assert_eq!(trie.lookup(0x0A01_0204), None);
+        }
+
+        #[test]
+        fn default_route_matches_everything() {
+            let mut trie = PatriciaTrie::<u32, u32>::default();
+            trie.insert(0, 0, 1);
+            assert_eq!(trie.lookup(0xFFFF_FFFF), Some(1));
+            assert_eq!(trie.lookup(0), Some(1));
+        }
+
+        #[test]
+        fn remove_prunes_leaf_and_falls_back_to_covering_prefix() {
+            let mut trie = PatriciaTrie::<u32, u32>::default();
+            trie.insert(0x0A00_0000, 8, 1);
+            trie.insert(0x0A01_0000, 16, 2);
+            trie.remove(0x0A01_0000, 16);
+            assert_eq!(trie.lookup(0x0A01_0203), Some(1));
+        }
+
+        #[test]
+        fn remove_keeps_branch_alive_when_sibling_remains() {
+            let mut trie = PatriciaTrie::<u32, u32>::default();
+            trie.insert(0x0A00_0000, 16, 1);
+            trie.insert(0x0B00_0000, 16, 2);
+            trie.remove(0x0A00_0000, 16);
+            assert_eq!(trie.lookup(0x0A00_0203), None);
+            assert_eq!(trie.lookup(0x0B00_0203), Some(2));
+        }
+
+        #[test]
+        fn fuzz_against_linear_scan_oracle() {
+            let mut rng = Xorshift(0xC0FF_EE01_DEAD_BEEF);
+            let mut trie = PatriciaTrie::<u32, u32>::default();
+            let mut routes: Vec<(u32, u8, u32)> = Vec::new();
+
+            for i in 0..2000u32 {
+                match rng.next() % 3 {
+                    0 if !routes.is_empty() => {
+                        let idx = (rng.next() as usize) % routes.len();
+                        let (prefix, len, _) = routes.swap_remove(idx);
+                        trie.remove(prefix, len);
+                    }
+                    _ => {
+                        let prefix = rng.next_u32();
+                        let len = rng.next_prefix_len();
+                        routes.retain(|(p, l, _)| !(p.mask(*l) == prefix.mask(len) && *l == len));
+                        routes.push((prefix, len, i));
+                        trie.insert(prefix, len, i);
+                    }
+                }
+
+                let probe = rng.next_u32();
+                assert_eq!(trie.lookup(probe), oracle_lookup(&routes, probe));
+            }
+        }
+    }
+}
+
+/// A single shard of the routing table: an independently-lockable slice of the authoritative
+/// store, together with the slice of each trie accelerator indexing its own subnets. Every
+/// [`RouteKey`] for a given subnet always lives in the same shard, so per-subnet operations
+/// (selection, relay discovery, reindexing) only ever need to lock one of them, and a
+/// control-plane write to one shard's trie never blocks a reindex of another.
+#[derive(Debug, Default)]
+struct Shard {
+    // Keyed by `(Subnet, IpAddr)` rather than `RouteKey` itself, so that every entry for a given
+    // subnet occupies one contiguous range: `RouteKey`'s `Ord` impl already compares
+    // `(subnet, neighbor.overlay_ip())` lexicographically, and the tuple form lets per-subnet
+    // operations bound a `range`/`range_mut` query with plain `IpAddr` sentinels instead of
+    // having to construct a real `RouteKey` (which needs a [`Peer`]) as a bound.
+    table: BTreeMap<(Subnet, IpAddr), RouteEntry>,
+    v4_trie: trie::PatriciaTrie<u32, RouteKey>,
+    v6_trie: trie::PatriciaTrie<u128, RouteKey>,
+}
+
+impl Shard {
+    /// The inclusive key range covering every entry stored for `subnet`. Since entries are keyed
+    /// by `(Subnet, IpAddr)` and `IpAddr` orders every IPv4 address before every IPv6 address,
+    /// bounding the neighbour-IP component by the lowest IPv4 and the highest IPv6 address
+    /// covers exactly `subnet`'s entries, whatever the address family of each neighbour.
+    fn subnet_bounds(subnet: Subnet) -> RangeInclusive<(Subnet, IpAddr)> {
+        let min_ip = IpAddr::V4(Ipv4Addr::UNSPECIFIED);
+        let max_ip = IpAddr::V6(Ipv6Addr::from(u128::MAX));
+        (subnet, min_ip)..=(subnet, max_ip)
+    }
+
+    /// Returns the [`RouteKey`] of the currently selected entry for `subnet` in this shard, if
+    /// any, by scanning only the range of entries belonging to that subnet rather than the whole
+    /// shard.
+    fn selected_key(&self, subnet: Subnet) -> Option<RouteKey> {
+        self.table
+            .range(Self::subnet_bounds(subnet))
+            .find(|(_, entry)| entry.selected())
+            .map(|(_, entry)| RouteKey::new(subnet, entry.neighbour().clone()))
+    }
+}
+
+/// Number of shards the routing table is split across. Control-plane writes to subnets in
+/// different shards can then proceed in parallel instead of serializing behind one lock. A
+/// forwarding-path lookup still has to probe every shard's trie, since which shard a subnet
+/// hashes into isn't known from the looked-up address alone, but those are all read locks.
+const SHARD_COUNT: usize = 16;
+
+#[derive(Debug, Clone, Copy)]
+struct Params {
+    // Margin a challenger route must beat the selected route by before select_route switches
+    // over to it, to avoid flapping between routes of near-identical cost.
+    hysteresis_margin: Metric,
+    // How long a retracted entry is kept around before `tick` removes it, giving neighbours
+    // time to observe the retraction.
+    gc_delay: Duration,
+}
+
+/// Default hysteresis margin used by [`RoutingTable::select_route`].
+const DEFAULT_HYSTERESIS_MARGIN: u16 = 32;
+
+/// Default delay, after an entry is retracted, before [`RoutingTable::tick`] collects it.
+const DEFAULT_GC_DELAY: Duration = Duration::from_secs(60);
+
+#[derive(Debug)]
 pub struct RoutingTable {
-    // TODO: we might need a better structure for this.
-    table: BTreeMap<RouteKey, RouteEntry>,
+    // The authoritative store, sharded so that forwarding-path reads and control-plane writes
+    // touching different subnets don't serialize behind a single lock. Each shard also carries
+    // its own slice of the trie accelerators, derived from that shard's table; they only ever
+    // hold `selected` routes and must never be consulted as the source of truth.
+    shards: Vec<RwLock<Shard>>,
+    // Per-source feasibility distances, enforcing the Babel loop-avoidance invariant.
+    source_table: RwLock<SourceTable>,
+    params: RwLock<Params>,
+}
+
+/// A write handle on a single [`RouteEntry`], held via its shard's lock. Dereferences to the
+/// entry; dropping the guard releases the shard lock and refreshes the forwarding-path
+/// accelerator for the entry's subnet, so a caller that flips `selected` through this handle
+/// doesn't need to remember to call [`RoutingTable::select_route`] or otherwise reindex.
+pub struct RouteEntryGuard<'a> {
+    table: &'a RoutingTable,
+    guard: Option<RwLockWriteGuard<'a, Shard>>,
+    key: RouteKey,
+}
+
+impl Deref for RouteEntryGuard<'_> {
+    type Target = RouteEntry;
+
+    fn deref(&self) -> &RouteEntry {
+        self.guard
+            .as_ref()
+            .expect("guard only taken in Drop")
+            .table
+            .get(&self.key.table_key())
+            .expect("entry present for the lifetime of the guard")
+    }
+}
+
+impl DerefMut for RouteEntryGuard<'_> {
+    fn deref_mut(&mut self) -> &mut RouteEntry {
+        self.guard
+            .as_mut()
+            .expect("guard only taken in Drop")
+            .table
+            .get_mut(&self.key.table_key())
+            .expect("entry present for the lifetime of the guard")
+    }
+}
+
+impl Drop for RouteEntryGuard<'_> {
+    fn drop(&mut self) {
+        // Release the shard lock before reindex_subnet tries to reacquire it, to avoid
+        // deadlocking against ourselves.
+        self.guard.take();
+        self.table.reindex_subnet(self.key.subnet());
+    }
+}
+
+/// Adds `margin` to `metric`, saturating at [`Metric::infinite()`] instead of wrapping or
+/// panicking if the sum would reach or overflow it. A challenger's effective metric can
+/// legitimately sit close to the infinite sentinel, so `select_route`'s hysteresis comparison
+/// must not let that addition wrap around into a small value and produce a bogus comparison.
+fn saturating_add_margin(metric: Metric, margin: Metric) -> Metric {
+    if metric.is_infinite() || margin.is_infinite() {
+        return Metric::infinite();
+    }
+    let sum = metric + margin;
+    if sum.is_infinite() || sum < metric || sum < margin {
+        Metric::infinite()
+    } else {
+        sum
+    }
 }
 
 impl RoutingTable {
     /// Create a new, empty `RoutingTable`.
     pub fn new() -> Self {
         Self {
-            table: BTreeMap::new(),
+            shards: (0..SHARD_COUNT).map(|_| RwLock::new(Shard::default())).collect(),
+            source_table: RwLock::new(SourceTable::new()),
+            params: RwLock::new(Params {
+                hysteresis_margin: Metric::from(DEFAULT_HYSTERESIS_MARGIN),
+                gc_delay: DEFAULT_GC_DELAY,
+            }),
+        }
+    }
+
+    /// Returns the shard holding every [`RouteKey`] for `subnet`.
+    fn shard(&self, subnet: Subnet) -> &RwLock<Shard> {
+        let mut hasher = DefaultHasher::new();
+        subnet.hash(&mut hasher);
+        &self.shards[(hasher.finish() as usize) % self.shards.len()]
+    }
+
+    /// Checks the Babel feasibility condition for an update with the given `seqno` and `metric`,
+    /// advertised for `source`. An infeasible update must never be selected, though it can still
+    /// be stored as an unselected [`RouteEntry`].
+    pub fn is_feasible(&self, source: SourceKey, seqno: SeqNo, metric: Metric) -> bool {
+        self.source_table.read().unwrap().is_feasible(source, seqno, metric)
+    }
+
+    /// Sets the hysteresis margin used by [`select_route`](Self::select_route). A challenger
+    /// route only displaces the currently selected one if it improves the effective metric by
+    /// at least this much.
+    pub fn set_hysteresis_margin(&self, margin: Metric) {
+        self.params.write().unwrap().hysteresis_margin = margin;
+    }
+
+    /// Sets the delay [`tick`](Self::tick) waits after an entry is retracted before collecting
+    /// it.
+    pub fn set_gc_delay(&self, gc_delay: Duration) {
+        self.params.write().unwrap().gc_delay = gc_delay;
+    }
+
+    /// Resets the expiry timer of the [`RouteEntry`] at `key`, as if a fresh update had just
+    /// been received for it from its neighbour. Returns whether a matching entry was found.
+    pub fn refresh(&self, key: &RouteKey) -> bool {
+        match self
+            .shard(key.subnet())
+            .write()
+            .unwrap()
+            .table
+            .get_mut(&key.table_key())
+        {
+            Some(entry) => {
+                entry.refresh();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Checks whether `subnet` currently has a selected route.
+    pub fn has_selected_route(&self, subnet: Subnet) -> bool {
+        self.shard(subnet)
+            .read()
+            .unwrap()
+            .table
+            .range(Shard::subnet_bounds(subnet))
+            .any(|(_, v)| v.selected())
+    }
+
+    /// Returns the directly connected peers in `directly_connected_peers` that currently
+    /// advertise a feasible route toward `subnet`, ranked from lowest to highest effective
+    /// metric (the advertised metric plus the link cost to the peer).
+    ///
+    /// Intended for use when `subnet` has no selected route: instead of dropping packets, the
+    /// forwarding layer can splice them through the best-ranked candidate as a relay, and fall
+    /// back to the next one if that fails.
+    pub fn potential_relays(&self, subnet: Subnet, directly_connected_peers: &[Peer]) -> Vec<Peer> {
+        let source_table = self.source_table.read().unwrap();
+        let shard = self.shard(subnet).read().unwrap();
+
+        let mut candidates: Vec<(Peer, Metric)> = shard
+            .table
+            .range(Shard::subnet_bounds(subnet))
+            .filter(|(_, entry)| {
+                !entry.metric().is_infinite()
+                    && source_table.is_feasible(entry.source(), entry.seqno(), entry.metric())
+            })
+            .filter(|(_, entry)| directly_connected_peers.contains(entry.neighbour()))
+            .map(|(_, entry)| {
+                let effective = entry.metric() + entry.neighbour().link_cost();
+                (entry.neighbour().clone(), effective)
+            })
+            .collect();
+
+        candidates.sort_by_key(|(_, effective)| *effective);
+        candidates.into_iter().map(|(peer, _)| peer).collect()
+    }
+
+    /// Advances every [`RouteEntry`]'s expiry timer to `now`: entries whose hold time has
+    /// elapsed are retracted (their metric is set to infinite), and entries that have stayed
+    /// retracted for at least the configured GC delay are removed entirely.
+    ///
+    /// Returns the set of subnets whose `selected` route expired as part of this call, so the
+    /// caller can re-run [`select_route`](Self::select_route) for them and emit retraction
+    /// updates to neighbours.
+    pub fn tick(&self, now: Instant) -> BTreeSet<Subnet> {
+        let mut expired_subnets = BTreeSet::new();
+        let gc_delay = self.params.read().unwrap().gc_delay;
+
+        for shard_lock in &self.shards {
+            // Only the subnets actually touched this tick need their accelerator refreshed: one
+            // whose selected entry just expired (its `selected` flag flipped to false), or one
+            // that had an entry collected outright (its trie node may need pruning). Every other
+            // subnet in the shard is untouched, so reindexing it would just repeat the same
+            // result for nothing.
+            let mut subnets_to_reindex = BTreeSet::new();
+            {
+                let mut shard = shard_lock.write().unwrap();
+                for ((subnet, _), entry) in shard.table.iter_mut() {
+                    if entry.expired(now) {
+                        let was_selected = entry.selected();
+                        entry.retract(now);
+                        if was_selected {
+                            expired_subnets.insert(*subnet);
+                            subnets_to_reindex.insert(*subnet);
+                        }
+                    }
+                }
+                shard.table.retain(|(subnet, _), entry| {
+                    let collectible = entry.collectible(now, gc_delay);
+                    if collectible {
+                        subnets_to_reindex.insert(*subnet);
+                    }
+                    !collectible
+                });
+            }
+
+            for subnet in subnets_to_reindex {
+                self.reindex_subnet(subnet);
+            }
         }
+
+        expired_subnets
     }
 
-    /// Get a reference to the [`RouteEntry`] associated with the [`RouteKey`] if one is present in
+    /// Runs Babel route selection for `subnet`: among its feasible, non-retracted entries,
+    /// picks the one with the lowest effective metric (the advertised metric plus the link cost
+    /// to its neighbour), breaking ties by source `RouterId` and then neighbour overlay IP.
+    ///
+    /// To avoid route flapping, a route other than the one already selected is only chosen if
+    /// it improves on the current effective metric by at least the configured hysteresis
+    /// margin, unless the currently selected route has become infeasible or retracted, in which
+    /// case the best remaining candidate is chosen unconditionally.
+    ///
+    /// Returns the [`RouteKey`] of the newly selected route, or `None` if `subnet` has become
+    /// unreachable.
+    pub fn select_route(&self, subnet: Subnet) -> Option<RouteKey> {
+        let hysteresis_margin = self.params.read().unwrap().hysteresis_margin;
+
+        // Acquire source_table before the shard lock, matching the order `potential_relays` and
+        // `insert` already use: taking them in the opposite order here (as a prior version of
+        // this function did) can deadlock against a concurrent caller of those.
+        let source_table = self.source_table.read().unwrap();
+
+        let (chosen, feasibility_update) = {
+            let mut shard = self.shard(subnet).write().unwrap();
+
+            let candidates: Vec<(RouteKey, RouteEntry)> = shard
+                .table
+                .range(Shard::subnet_bounds(subnet))
+                .map(|(_, v)| (RouteKey::new(subnet, v.neighbour().clone()), v.clone()))
+                .collect();
+
+            let current = candidates.iter().find(|(_, v)| v.selected()).cloned();
+
+            // The selected entry whose (seqno, metric) is itself its source's feasibility distance
+            // stays feasible; only other updates must strictly improve on that distance.
+            let is_feasible = |entry: &RouteEntry| {
+                !entry.metric().is_infinite()
+                    && (source_table.is_feasible(entry.source(), entry.seqno(), entry.metric())
+                        || (entry.selected()
+                            && source_table.feasibility_distance(entry.source())
+                                == Some((entry.seqno(), entry.metric()))))
+            };
+            let effective_metric =
+                |entry: &RouteEntry| entry.metric() + entry.neighbour().link_cost();
+            let tie_break = |key: &RouteKey, entry: &RouteEntry| {
+                (entry.source().router_id(), key.neighbor.overlay_ip())
+            };
+
+            let mut best: Option<(RouteKey, RouteEntry, Metric)> = None;
+            for (key, entry) in candidates.iter().filter(|(_, v)| is_feasible(v)) {
+                let effective = effective_metric(entry);
+                let better = match &best {
+                    None => true,
+                    Some((best_key, best_entry, best_effective)) => {
+                        match effective.cmp(best_effective) {
+                            Ordering::Less => true,
+                            Ordering::Greater => false,
+                            Ordering::Equal => {
+                                tie_break(key, entry) < tie_break(best_key, best_entry)
+                            }
+                        }
+                    }
+                };
+                if better {
+                    best = Some((key.clone(), entry.clone(), effective));
+                }
+            }
+
+            let current_is_viable = current.as_ref().is_some_and(|(_, entry)| is_feasible(entry));
+
+            let chosen = match (&current, &best) {
+                (Some((current_key, current_entry)), Some((best_key, _, best_effective))) => {
+                    if !current_is_viable
+                        || current_key == best_key
+                        || saturating_add_margin(*best_effective, hysteresis_margin)
+                            <= effective_metric(current_entry)
+                    {
+                        Some(best_key.clone())
+                    } else {
+                        Some(current_key.clone())
+                    }
+                }
+                (_, Some((best_key, _, _))) => Some(best_key.clone()),
+                (_, None) => None,
+            };
+
+            for (key, _) in &candidates {
+                let want_selected = chosen.as_ref() == Some(key);
+                if let Some(e) = shard.table.get_mut(&key.table_key()) {
+                    if e.selected() != want_selected {
+                        e.set_selected(want_selected);
+                    }
+                }
+            }
+
+            // A selected route's (seqno, metric) becomes the new feasibility distance floor for
+            // its source, but that write needs `source_table.write()`, which must not be taken
+            // while its own read guard above, or this shard's write guard, are still held.
+            // Extract the values needed and apply the update once both locks are released below.
+            let feasibility_update = chosen.as_ref().and_then(|key| {
+                shard
+                    .table
+                    .get(&key.table_key())
+                    .map(|entry| (entry.source(), entry.seqno(), entry.metric()))
+            });
+
+            (chosen, feasibility_update)
+        };
+
+        drop(source_table);
+
+        if let Some((source, seqno, metric)) = feasibility_update {
+            self.source_table
+                .write()
+                .unwrap()
+                .update_feasibility_distance(source, seqno, metric);
+        }
+
+        self.reindex_subnet(subnet);
+        chosen
+    }
+
+    /// Get a copy of the [`RouteEntry`] associated with the [`RouteKey`] if one is present in
     /// the table.
-    pub fn get(&self, key: &RouteKey) -> Option<&RouteEntry> {
-        self.table.get(key)
+    pub fn get(&self, key: &RouteKey) -> Option<RouteEntry> {
+        self.shard(key.subnet())
+            .read()
+            .unwrap()
+            .table
+            .get(&key.table_key())
+            .cloned()
     }
 
-    /// Get a mutablereference to the [`RouteEntry`] associated with the [`RouteKey`] if one is
-    /// present in the table.
-    pub fn get_mut(&mut self, key: &RouteKey) -> Option<&mut RouteEntry> {
-        self.table.get_mut(key)
+    /// Get a write handle on the [`RouteEntry`] associated with the [`RouteKey`] if one is
+    /// present in the table. The handle holds the entry's shard locked until dropped, at which
+    /// point the subnet's forwarding-path accelerator is refreshed.
+    pub fn get_mut(&self, key: &RouteKey) -> Option<RouteEntryGuard<'_>> {
+        let guard = self.shard(key.subnet()).write().unwrap();
+        if guard.table.contains_key(&key.table_key()) {
+            Some(RouteEntryGuard {
+                table: self,
+                guard: Some(guard),
+                key: key.clone(),
+            })
+        } else {
+            None
+        }
     }
 
     /// Insert a new [`RouteEntry`] in the table. If there is already an entry for the
     /// [`RouteKey`], the existing entry is removed.
-    pub fn insert(&mut self, key: RouteKey, entry: RouteEntry) {
-        self.table.insert(key, entry);
+    pub fn insert(&self, key: RouteKey, mut entry: RouteEntry) {
+        let subnet = key.subnet();
+
+        // A selected route must be feasible by construction: downgrade rather than trust a
+        // caller that built a `RouteEntry` with `selected: true` directly, bypassing
+        // `select_route`, since nothing else in `RoutingTable` would otherwise catch that.
+        if entry.selected()
+            && (entry.metric().is_infinite()
+                || !self.source_table.read().unwrap().is_feasible(
+                    entry.source(),
+                    entry.seqno(),
+                    entry.metric(),
+                ))
+        {
+            entry.set_selected(false);
+        }
+
+        // A selected route is by construction feasible, so its (seqno, metric) becomes the new
+        // feasibility distance floor for its source.
+        if entry.selected() {
+            self.source_table.write().unwrap().update_feasibility_distance(
+                entry.source(),
+                entry.seqno(),
+                entry.metric(),
+            );
+        }
+        self.shard(subnet)
+            .write()
+            .unwrap()
+            .table
+            .insert(key.table_key(), entry);
+        self.reindex_subnet(subnet);
     }
 
     /// Make sure there is no [`RouteEntry`] in the table for a given [`RouteKey`]. If an entry
     /// existed prior to calling this, it is returned.
-    pub fn remove(&mut self, key: &RouteKey) -> Option<RouteEntry> {
-        self.table.remove(key)
+    pub fn remove(&self, key: &RouteKey) -> Option<RouteEntry> {
+        let removed = self
+            .shard(key.subnet())
+            .write()
+            .unwrap()
+            .table
+            .remove(&key.table_key());
+        if removed.is_some() {
+            self.reindex_subnet(key.subnet());
+        }
+        removed
     }
 
-    /// Create an iterator over all key value pairs in the table.
+    /// Returns a snapshot of every key/value pair currently in the table.
     // TODO: remove this?
-    pub fn iter(&self) -> impl Iterator<Item = (&'_ RouteKey, &'_ RouteEntry)> {
-        self.table.iter()
+    pub fn iter(&self) -> Vec<(RouteKey, RouteEntry)> {
+        self.shards
+            .iter()
+            .flat_map(|shard| {
+                shard
+                    .read()
+                    .unwrap()
+                    .table
+                    .iter()
+                    .map(|((subnet, _), v)| {
+                        (RouteKey::new(*subnet, v.neighbour().clone()), v.clone())
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect()
     }
 
     /// Checks if there is an entry for the given [`RouteKey`].
     pub fn contains_key(&self, key: &RouteKey) -> bool {
-        self.table.contains_key(key)
+        self.shard(key.subnet())
+            .read()
+            .unwrap()
+            .table
+            .contains_key(&key.table_key())
     }
 
     /// Only maintain the [`RouteEntry`]'s indicated by the predicate.
-    pub fn retain<F>(&mut self, f: F)
+    pub fn retain<F>(&self, mut f: F)
     where
         F: FnMut(&RouteKey, &mut RouteEntry) -> bool,
     {
-        self.table.retain(f)
+        for shard_lock in &self.shards {
+            // Only subnets whose selection actually flips, or that lose an entry outright, need
+            // their accelerator refreshed; a bulk retain that leaves everything else untouched
+            // shouldn't pay to reindex every subnet present in the shard on every call. The write
+            // lock is released before reindexing to avoid re-entering it from `reindex_subnet`.
+            let mut subnets_to_reindex = BTreeSet::new();
+            {
+                let mut shard = shard_lock.write().unwrap();
+                shard.table.retain(|(subnet, _), entry| {
+                    let key = RouteKey::new(*subnet, entry.neighbour().clone());
+                    let was_selected = entry.selected();
+                    let keep = f(&key, entry);
+                    if !keep || entry.selected() != was_selected {
+                        subnets_to_reindex.insert(*subnet);
+                    }
+                    keep
+                });
+            }
+            for subnet in subnets_to_reindex {
+                self.reindex_subnet(subnet);
+            }
+        }
     }
 
     /// Look up a route for an [`IpAddr`] in the `RoutingTable`.
+    ///
+    /// This performs a longest-prefix-match against the currently `selected` routes, using the
+    /// trie accelerators, and is therefore O(prefix-bits) per shard. Since which shard a subnet
+    /// hashes into can't be derived from the looked-up address, every shard's trie is probed and
+    /// the most specific match across all of them wins; each probe is only a read lock, so this
+    /// still never blocks on, or behind, a control-plane write to another shard.
     pub fn lookup(&self, ip: IpAddr) -> Option<RouteEntry> {
-        for (rk, rv) in &self.table {
-            if rk.subnet.contains_ip(ip) {
-                return Some(rv.clone());
+        let mut best: Option<(RouteKey, u8)> = None;
+        for shard_lock in &self.shards {
+            let shard = shard_lock.read().unwrap();
+            let candidate = match ip {
+                IpAddr::V4(addr) => shard.v4_trie.lookup(u32::from(addr)),
+                IpAddr::V6(addr) => shard.v6_trie.lookup(u128::from(addr)),
+            };
+            if let Some(key) = candidate {
+                let prefix_len = key.subnet().prefix_len();
+                let is_more_specific = match &best {
+                    Some((_, best_len)) => prefix_len > *best_len,
+                    None => true,
+                };
+                if is_more_specific {
+                    best = Some((key, prefix_len));
+                }
             }
         }
 
-        None
+        let (key, _) = best?;
+        self.shard(key.subnet())
+            .read()
+            .unwrap()
+            .table
+            .get(&key.table_key())
+            .cloned()
+    }
+
+    /// Refreshes the forwarding-path accelerator for `subnet` from the authoritative shard,
+    /// indexing the currently `selected` entry (if any) or pruning a stale one.
+    fn reindex_subnet(&self, subnet: Subnet) {
+        let mut shard = self.shard(subnet).write().unwrap();
+        let selected = shard.selected_key(subnet);
+
+        match selected {
+            Some(key) => match subnet.address() {
+                IpAddr::V4(addr) => shard.v4_trie.insert(u32::from(addr), subnet.prefix_len(), key),
+                IpAddr::V6(addr) => shard.v6_trie.insert(u128::from(addr), subnet.prefix_len(), key),
+            },
+            None => match subnet.address() {
+                IpAddr::V4(addr) => shard.v4_trie.remove(u32::from(addr), subnet.prefix_len()),
+                IpAddr::V6(addr) => shard.v6_trie.remove(u128::from(addr), subnet.prefix_len()),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod hysteresis_tests {
+    use super::*;
+
+    #[test]
+    fn ordinary_sum_is_unaffected() {
+        assert_eq!(
+            saturating_add_margin(Metric::from(100), Metric::from(32)),
+            Metric::from(132)
+        );
+    }
+
+    #[test]
+    fn sum_at_the_infinite_sentinel_saturates() {
+        let near_ceiling = Metric::from(0xFFFE);
+        assert_eq!(
+            saturating_add_margin(near_ceiling, Metric::from(32)),
+            Metric::infinite()
+        );
+    }
+
+    #[test]
+    fn either_infinite_operand_saturates() {
+        assert_eq!(
+            saturating_add_margin(Metric::infinite(), Metric::from(32)),
+            Metric::infinite()
+        );
+        assert_eq!(
+            saturating_add_margin(Metric::from(10), Metric::infinite()),
+            Metric::infinite()
+        );
+    }
+
+    #[test]
+    fn zero_margin_is_a_no_op() {
+        assert_eq!(
+            saturating_add_margin(Metric::from(500), Metric::from(0)),
+            Metric::from(500)
+        );
     }
 }
@@ -0,0 +1,101 @@
+use crate::{metric::Metric, router_id::RouterId, sequence_number::SeqNo, subnet::Subnet};
+use std::collections::HashMap;
+
+/// Identifies the source of a route: the subnet being advertised, together with the router
+/// which originally injected it into the network.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SourceKey {
+    subnet: Subnet,
+    router_id: RouterId,
+}
+
+impl SourceKey {
+    /// Create a new `SourceKey` for the given [`Subnet`] and [`RouterId`].
+    pub const fn new(subnet: Subnet, router_id: RouterId) -> Self {
+        Self { subnet, router_id }
+    }
+
+    /// Returns the [`Subnet`] associated with this `SourceKey`.
+    pub const fn subnet(&self) -> Subnet {
+        self.subnet
+    }
+
+    /// Returns the [`RouterId`] associated with this `SourceKey`.
+    pub const fn router_id(&self) -> RouterId {
+        self.router_id
+    }
+
+    /// Updates the [`RouterId`] of this `SourceKey` to the given value.
+    pub fn set_router_id(&mut self, router_id: RouterId) {
+        self.router_id = router_id;
+    }
+}
+
+/// The feasibility distance recorded for a [`SourceKey`]: the best `(seqno, metric)` pair seen
+/// so far among feasible, selected routes from that source. Per the Babel specification, this
+/// value is a monotonic lower bound and is never raised, only refined.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct FeasibilityDistance {
+    seqno: SeqNo,
+    metric: Metric,
+}
+
+/// Tracks, per [`SourceKey`], the feasibility distance used to enforce the Babel loop-avoidance
+/// invariant: a route is only accepted as an improvement over what has already been seen from
+/// its source.
+#[derive(Debug, Clone, Default)]
+pub struct SourceTable {
+    table: HashMap<SourceKey, FeasibilityDistance>,
+}
+
+impl SourceTable {
+    /// Create a new, empty `SourceTable`.
+    pub fn new() -> Self {
+        Self {
+            table: HashMap::new(),
+        }
+    }
+
+    /// Returns the feasibility distance currently recorded for `source`, if any.
+    pub fn feasibility_distance(&self, source: SourceKey) -> Option<(SeqNo, Metric)> {
+        self.table.get(&source).map(|fd| (fd.seqno, fd.metric))
+    }
+
+    /// Implements the Babel feasibility condition for an update with the given `seqno` and
+    /// `metric`, advertised for `source`.
+    ///
+    /// An update is feasible if it is a retraction (`metric` is infinite), if no feasibility
+    /// distance is recorded yet for `source`, if `seqno` is strictly newer than the recorded
+    /// seqno (serial-number comparison, i.e. wrapping modulo 2^16), or if the seqnos are equal
+    /// and `metric` strictly improves on the recorded metric.
+    pub fn is_feasible(&self, source: SourceKey, seqno: SeqNo, metric: Metric) -> bool {
+        if metric.is_infinite() {
+            return true;
+        }
+
+        match self.table.get(&source) {
+            None => true,
+            Some(fd) => seqno > fd.seqno || (seqno == fd.seqno && metric < fd.metric),
+        }
+    }
+
+    /// Updates the feasibility distance for `source` with a newly selected `(seqno, metric)`
+    /// pair. The feasibility distance is a lower bound and is only ever refined, never raised:
+    /// a strictly newer seqno always replaces it, while for an equal seqno only a strictly
+    /// better metric does.
+    pub fn update_feasibility_distance(&mut self, source: SourceKey, seqno: SeqNo, metric: Metric) {
+        match self.table.get_mut(&source) {
+            None => {
+                self.table.insert(source, FeasibilityDistance { seqno, metric });
+            }
+            Some(fd) => {
+                if seqno > fd.seqno {
+                    fd.seqno = seqno;
+                    fd.metric = metric;
+                } else if seqno == fd.seqno && metric < fd.metric {
+                    fd.metric = metric;
+                }
+            }
+        }
+    }
+}